@@ -0,0 +1,36 @@
+//! Small modular-arithmetic helpers shared by [`crate::threshold`] and
+//! [`crate::crt`]: both reduce `StaticUnsignedBigInt<N>` values down to
+//! `u128` to do Lagrange/CRT arithmetic in plain integers, then need a
+//! modular inverse to finish the reconstruction.
+
+use tfhe::integer::bigint::static_unsigned::StaticUnsignedBigInt;
+
+/// The modular inverse of `a` mod `m`, via the extended Euclidean algorithm.
+/// Only defined when `gcd(a, m) == 1`; panics otherwise rather than
+/// returning a value that looks plausible but silently isn't an inverse at
+/// all (e.g. `mod_inverse(2, 4096)` has no real answer, since 2 isn't
+/// invertible mod a power of two).
+pub(crate) fn mod_inverse(a: u128, m: u128) -> u128 {
+    let (mut old_r, mut r) = (a as i128, m as i128);
+    let (mut old_s, mut s) = (1i128, 0i128);
+    while r != 0 {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_s, s) = (s, old_s - quotient * s);
+    }
+    assert_eq!(old_r.abs(), 1, "mod_inverse: gcd(a, m) != 1, no inverse exists");
+    (old_s.rem_euclid(m as i128)) as u128
+}
+
+/// Narrows a `StaticUnsignedBigInt<N>` to `u128`, for moduli small enough
+/// that Lagrange/CRT arithmetic can run in plain integers instead of
+/// bigint-land.
+pub(crate) fn to_u128<const N: usize>(x: StaticUnsignedBigInt<N>) -> u128 {
+    let n = (StaticUnsignedBigInt::<N>::BITS.div_ceil(u8::BITS)) as usize;
+    assert!(n <= 16, "modulus too wide for this crate's u128 arithmetic");
+    let mut buf = vec![0u8; n];
+    x.copy_to_le_byte_slice(&mut buf);
+    let mut bytes = [0u8; 16];
+    bytes[..n].copy_from_slice(&buf);
+    u128::from_le_bytes(bytes)
+}
@@ -0,0 +1,305 @@
+//! An alternate ciphertext encoding that represents lattice values in a
+//! residue-number-system (CRT) basis instead of the single wide radix used
+//! by [`crate::encode`]/[`crate::eval`]. Per the concrete-integer benches,
+//! `scalar_mul_parallelized` over several *narrow* CRT channels is
+//! considerably faster than over one wide radix ciphertext, provided each
+//! channel is actually encrypted at its own small width rather than the
+//! suite's full `q`-width radix — so unlike the non-CRT path, every channel
+//! here gets its own key pair, sized from [`CrtCipherSuite::CRT_BASIS`]'s
+//! corresponding modulus, from [`generate_crt_fhe_keys`].
+//!
+//! Each channel's accumulator (`LATTICE_DIM` products of two residues below
+//! `basis[i]`, summed before any reduction) is sized wide enough to hold the
+//! true, un-wrapped sum — see [`channel_num_blocks`] — and only then folded
+//! back mod `basis[i]` via a WoPBS lookup table, the same without-padding-
+//! bootstrap technique [`crate::eval_wopbs`] uses for its rounding step.
+//! Folding mod `basis[i]` *before* the accumulator is fully summed would
+//! wrap mod a power of two that generally isn't a multiple of `basis[i]`,
+//! corrupting the result. CRT reconstruction itself (`sum residue_i *
+//! weight_i mod M`, then mod `q`) only needs plain integers below `M`, so it
+//! happens client-side in [`decrypt_crt`] after each channel has been
+//! decrypted under its own key, rather than homomorphically across channels
+//! that don't share a key.
+//!
+//! The channels recover each row's integer inner product only mod `M`, so
+//! [`decrypt_crt`] reproduces [`crate::eval`]'s mod-`q` output only when `M`
+//! exceeds the largest possible inner product, `LATTICE_DIM * (q - 1)^2`;
+//! benchmark against [`crate::eval`] for your dimension before switching.
+
+use rayon::prelude::*;
+use tfhe::integer::bigint::static_unsigned::StaticUnsignedBigInt;
+use tfhe::integer::wopbs::WopbsKey;
+use tfhe::integer::{gen_keys_radix, RadixCiphertext, RadixClientKey, ServerKey};
+
+use crate::bigint_util::{mod_inverse, to_u128};
+use crate::suite::CipherSuite;
+use crate::{generate_wopbs_key, prg_seed, sample_h};
+
+/// A [`CipherSuite`] that additionally picks a CRT basis for [`encode_crt`]/
+/// [`eval_crt`]/[`decrypt_crt`]: pairwise-coprime small moduli whose product
+/// `M` exceeds `LATTICE_DIM * (q - 1)^2`, the largest inner product
+/// [`eval_crt`] can accumulate. A smaller `M` (even one above `q`) recovers
+/// that sum only mod `M`, from which [`decrypt_crt`] cannot get it mod `q`.
+pub trait CrtCipherSuite<const N: usize>: CipherSuite<N> {
+    const CRT_BASIS: &'static [u64];
+}
+
+/// `h`, CRT-decomposed: for each lattice row and coordinate, one small
+/// ciphertext per [`CrtCipherSuite::CRT_BASIS`] channel instead of one wide
+/// radix ciphertext.
+pub type CrtRows = Vec<Vec<Vec<RadixCiphertext>>>;
+
+/// One [`CrtCipherSuite::CRT_BASIS`] channel's key material: a client/server
+/// key pair generated wide enough to hold that channel's whole pre-fold
+/// accumulator (see [`channel_num_blocks`], via [`generate_crt_fhe_keys`]), plus the WoPBS key [`eval_crt`] uses to fold
+/// a channel's sum back into `0..basis[i]`.
+pub struct CrtChannelKeys {
+    pub client_key: RadixClientKey,
+    pub server_key: ServerKey,
+    pub wopbs_key: WopbsKey,
+}
+
+/// The number of radix blocks needed to hold a channel's *pre-fold*
+/// accumulator under `S::FHE_PARAMS`'s message modulus: [`eval_crt`] sums
+/// `S::LATTICE_DIM` products of two residues each below `basis` before
+/// folding anything back mod `basis`, so the ciphertext has to be wide
+/// enough for that whole accumulation (up to `LATTICE_DIM * (basis - 1)^2`)
+/// to avoid wrapping — sizing it to `basis - 1` alone (as if `fold_mod` ran
+/// after every multiply) silently wraps mod a power of two that generally
+/// isn't a multiple of `basis`, corrupting the fold. Narrower than
+/// [`crate::generate_fhe_keys`]'s `q`-sized blocks only when this headroom
+/// needs fewer blocks than `LOG2Q` bits do; otherwise as wide or wider.
+fn channel_num_blocks<const N: usize, S: CipherSuite<N>>(basis: u64) -> usize {
+    let max_acc = (S::LATTICE_DIM as u128) * (basis - 1) as u128 * (basis - 1) as u128;
+    let bits = u128::BITS - max_acc.leading_zeros();
+    (bits as usize).div_ceil(S::FHE_PARAMS.message_modulus.0.ilog2() as usize)
+}
+
+/// Generates one key pair per [`CrtCipherSuite::CRT_BASIS`] channel, each
+/// sized from that channel's modulus (see [`channel_num_blocks`]) rather
+/// than from the suite's `q`.
+/// Expensive; generate once and reuse across [`encode_crt`]/[`eval_crt`]/
+/// [`decrypt_crt`] calls.
+pub fn generate_crt_fhe_keys<const N: usize, S: CrtCipherSuite<N>>() -> Vec<CrtChannelKeys> {
+    S::CRT_BASIS
+        .iter()
+        .map(|&basis| {
+            let (ck, sk) = gen_keys_radix(S::FHE_PARAMS, channel_num_blocks::<N, S>(basis));
+            let wopbs_key = generate_wopbs_key::<N, S>(&ck, &sk);
+            CrtChannelKeys {
+                client_key: ck,
+                server_key: sk,
+                wopbs_key,
+            }
+        })
+        .collect()
+}
+
+/// Like [`crate::encode`], but encrypts each lattice coordinate as its CRT
+/// residues, one per `channels[i]`'s own client key, rather than as a single
+/// wide radix value under one shared key.
+pub fn encode_crt<const N: usize, S: CrtCipherSuite<N>>(
+    channels: &[CrtChannelKeys],
+    x: &[u8],
+) -> CrtRows {
+    let seed = prg_seed::<N, S>(x);
+    sample_h::<N, S>(seed)
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|v| {
+                    to_crt_residues(to_u128(v), S::CRT_BASIS)
+                        .into_iter()
+                        .zip(channels)
+                        .map(|(r, channel)| channel.client_key.encrypt(r))
+                        .collect()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Like [`crate::eval`], but runs the encrypted matrix-vector product one
+/// CRT channel at a time, each under its own narrow `channels[i]` key; the
+/// LWR rounding shift and CRT reconstruction happen afterwards, client-side,
+/// in [`decrypt_crt`].
+pub fn eval_crt<const N: usize, S: CrtCipherSuite<N>>(
+    channels: &[CrtChannelKeys],
+    prf_key: &[StaticUnsignedBigInt<N>],
+    h: &CrtRows,
+) -> CrtRows {
+    let basis = S::CRT_BASIS;
+    let prf_key_residues: Vec<Vec<u64>> = prf_key
+        .iter()
+        .map(|&k| to_crt_residues(to_u128(k), basis))
+        .collect();
+
+    // Per-row, per-channel inner product: `sum_i h[row][i][channel] *
+    // prf_key_residues[i][channel]`, each channel independent of the rest
+    // and run under that channel's own narrow key.
+    h.par_iter()
+        .map(|row| {
+            (0..basis.len())
+                .map(|channel| {
+                    let CrtChannelKeys {
+                        server_key,
+                        wopbs_key,
+                        ..
+                    } = &channels[channel];
+                    let terms: Vec<_> = row
+                        .iter()
+                        .zip(&prf_key_residues)
+                        .map(|(coord, residues)| {
+                            server_key.scalar_mul_parallelized(&coord[channel], residues[channel])
+                        })
+                        .collect();
+                    let sum = server_key
+                        .unchecked_sum_ciphertexts_vec_parallelized(terms)
+                        .unwrap();
+                    fold_mod(wopbs_key, server_key, &sum, basis[channel])
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Reduces `x` mod `m` via a WoPBS lookup table, the same without-padding
+/// bootstrap [`crate::eval_wopbs`] uses for its rounding step — needed here
+/// because a channel's native ciphertext modulus is rounded up to a power of
+/// the suite's message modulus and so is generally wider than `basis[i]`.
+fn fold_mod(
+    wopbs_key: &WopbsKey,
+    server_key: &ServerKey,
+    x: &RadixCiphertext,
+    m: u64,
+) -> RadixCiphertext {
+    let x = wopbs_key.keyswitch_to_wopbs_params(server_key, x);
+    let lut = wopbs_key.generate_lut_radix(&x, |v| v % m);
+    let folded = wopbs_key.wopbs(&x, &lut);
+    wopbs_key.keyswitch_to_pbs_params(&folded)
+}
+
+/// `(M / m_i) * ((M / m_i)^-1 mod m_i) mod M`, the weight [`decrypt_crt`]
+/// multiplies channel `i`'s residue by so that summing all weighted
+/// residues yields the value mod `M = product(basis)` (the direct CRT
+/// formula).
+fn crt_weight(i: usize, basis: &[u64]) -> u128 {
+    let m: u128 = basis.iter().map(|&b| b as u128).product();
+    let b = basis[i] as u128;
+    let m_i = m / b;
+    let inv = mod_inverse(m_i % b, b);
+    (m_i * inv) % m
+}
+
+/// Decomposes `x` into its residues mod each of `basis`'s moduli.
+fn to_crt_residues(x: u128, basis: &[u64]) -> Vec<u64> {
+    basis.iter().map(|&m| (x % m as u128) as u64).collect()
+}
+
+/// Decrypts each channel under its own client key, CRT-reconstructs the row
+/// mod `M = product(basis)`, folds that down mod `q` (the non-CRT
+/// [`crate::eval`] path's arithmetic wraps mod `q` directly, via the
+/// ciphertext's native modulus; with `M` above every possible inner product,
+/// as [`CrtCipherSuite`] requires, the reconstruction is the exact sum and
+/// reducing it mod `q` matches that), then applies the same
+/// `LOG2Q - LOG2P` right shift [`crate::eval`] does. Reconstruction has to happen here in
+/// the clear, since the channels were never encrypted under a shared key
+/// that could combine them homomorphically.
+pub fn decrypt_crt<const N: usize, S: CrtCipherSuite<N>>(
+    channels: Vec<RadixClientKey>,
+    ct: &CrtRows,
+) -> Vec<u8> {
+    let basis = S::CRT_BASIS;
+    let q = 1u128 << S::LOG2Q;
+    let m: u128 = basis.iter().map(|&b| b as u128).product();
+    let p_bytes = S::LOG2P.div_ceil(u8::BITS as usize);
+
+    ct.par_iter()
+        .flat_map(|row_channels| {
+            let reconstructed: u128 = row_channels
+                .iter()
+                .enumerate()
+                .map(|(i, cti)| {
+                    let residue: u64 = channels[i].decrypt(cti);
+                    residue as u128 * crt_weight(i, basis)
+                })
+                .sum::<u128>()
+                % m;
+            let v = (reconstructed % q) as u64;
+
+            (v >> (S::LOG2Q - S::LOG2P)).to_le_bytes()[..p_bytes].to_vec()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        decrypt_crt, encode_crt, eval_crt, generate_crt_fhe_keys, mod_inverse, to_crt_residues,
+        CrtCipherSuite,
+    };
+    use crate::suite::{CipherSuite, DefaultSuite};
+    use crate::{decrypt, encode, eval, generate_fhe_keys, generate_prf_key};
+    use digest::Digest;
+    use tfhe::shortint::{ClassicPBSParameters, WopbsParameters};
+
+    #[test]
+    fn crt_residues_reconstruct_via_direct_crt_formula() {
+        let basis = [251u64, 253, 255]; // pairwise coprime
+        let m: u128 = basis.iter().map(|&b| b as u128).product();
+
+        for x in [0u128, 1, 12345, m - 1] {
+            let residues = to_crt_residues(x, &basis);
+
+            let mut acc = 0u128;
+            for (i, &r) in residues.iter().enumerate() {
+                let b = basis[i] as u128;
+                let m_i = m / b;
+                let inv = mod_inverse(m_i % b, b);
+                acc = (acc + r as u128 * m_i * inv) % m;
+            }
+            assert_eq!(acc, x);
+        }
+    }
+
+    /// A [`DefaultSuite`]-compatible suite with a CRT basis whose product
+    /// `13 * 17 * 19 * 23 * 29 * 31 * 37 = 3_212_440_751` exceeds
+    /// `LATTICE_DIM * (q - 1)^2 = 8 * 4095^2 = 134_152_200`, the largest
+    /// inner product under `DefaultSuite`'s `q = 2^12 = 4096`.
+    struct CrtDefaultSuite;
+
+    impl CipherSuite<1> for CrtDefaultSuite {
+        type Digest = sha2::Sha256;
+
+        const LATTICE_DIM: usize = DefaultSuite::LATTICE_DIM;
+        const LOG2Q: usize = DefaultSuite::LOG2Q;
+        const LOG2P: usize = DefaultSuite::LOG2P;
+        const OUT_LEN: usize = DefaultSuite::OUT_LEN;
+        const FHE_PARAMS: ClassicPBSParameters = DefaultSuite::FHE_PARAMS;
+        const WOPBS_PARAMS: WopbsParameters = DefaultSuite::WOPBS_PARAMS;
+    }
+
+    impl CrtCipherSuite<1> for CrtDefaultSuite {
+        const CRT_BASIS: &'static [u64] = &[13, 17, 19, 23, 29, 31, 37];
+    }
+
+    #[test]
+    fn crt_prf_matches_the_radix_eval_path() {
+        let (ck, sk) = generate_fhe_keys::<1, DefaultSuite>();
+        let pk = generate_prf_key::<1, DefaultSuite>();
+        let channels = generate_crt_fhe_keys::<1, CrtDefaultSuite>();
+
+        let x = vec![1, 2, 3];
+        let expected = decrypt::<1, DefaultSuite>(
+            ck.clone(),
+            &eval::<1, DefaultSuite>(&sk, &pk, &encode::<1, DefaultSuite>(&ck, &x)),
+        );
+
+        let x_enc = encode_crt::<1, CrtDefaultSuite>(&channels, &x);
+        let y = eval_crt::<1, CrtDefaultSuite>(&channels, &pk, &x_enc);
+        let client_keys = channels.into_iter().map(|c| c.client_key).collect();
+        let y_dec = decrypt_crt::<1, CrtDefaultSuite>(client_keys, &y);
+
+        assert_eq!(y_dec, expected);
+    }
+}
@@ -1,131 +1,193 @@
 use digest::Digest;
 use rayon::prelude::*;
-use tfhe::core_crypto::commons::math::random::{ActivatedRandomGenerator, RandomGenerator};
 use tfhe::core_crypto::seeders::new_seeder;
 use tfhe::integer::bigint::static_unsigned::StaticUnsignedBigInt;
+use tfhe::integer::wopbs::WopbsKey;
 use tfhe::integer::{gen_keys_radix, RadixCiphertext, RadixClientKey, ServerKey};
-use tfhe::shortint::parameters::PARAM_MESSAGE_2_CARRY_2_KS_PBS;
-use tfhe::shortint::ClassicPBSParameters;
+
+use sampler::UniformSampler;
 use vec::mat_mul_vec;
 
+mod bigint_util;
+pub mod crt;
+pub mod oprf;
+mod sampler;
+mod suite;
+pub mod threshold;
 mod vec;
 
-// PRF parameters.
-const LATTICE_DIM: usize = 8; // 512;
-const LOG2Q: usize = 12;
-const LOG2P: usize = 8;
-const OUT_LEN: usize = 16;
-
-// FHE parameters.
-const FHE_PARAMS: ClassicPBSParameters = PARAM_MESSAGE_2_CARRY_2_KS_PBS;
-const NUM_BLOCKS: usize = LOG2Q / FHE_PARAMS.message_modulus.0.ilog2() as usize;
-
-// Derived constants.
-const Q_BYTES: usize = LOG2Q.div_ceil(u8::BITS as usize);
-const P_BYTES: usize = LOG2P.div_ceil(u8::BITS as usize);
-const Q_BIGINT_SIZE: usize = Q_BYTES.div_ceil((u64::BITS / u8::BITS) as usize);
+pub use suite::{CipherSuite, DefaultSuite};
 
-// Derived types.
-type BigInt = StaticUnsignedBigInt<Q_BIGINT_SIZE>;
-
-pub fn generate_fhe_keys() -> (RadixClientKey, ServerKey) {
-    gen_keys_radix(PARAM_MESSAGE_2_CARRY_2_KS_PBS, NUM_BLOCKS)
+fn num_blocks<const N: usize, S: CipherSuite<N>>() -> usize {
+    S::LOG2Q / S::FHE_PARAMS.message_modulus.0.ilog2() as usize
 }
 
-fn generate_random_mod_q(rng: &mut RandomGenerator<ActivatedRandomGenerator>) -> BigInt {
-    let q = BigInt::from([1]) << LOG2Q as u32;
+pub fn generate_fhe_keys<const N: usize, S: CipherSuite<N>>() -> (RadixClientKey, ServerKey) {
+    gen_keys_radix(S::FHE_PARAMS, num_blocks::<N, S>())
+}
 
-    // Generate random value in 0..q.
-    let mut buf = [0u8; BigInt::BITS.div_ceil(u8::BITS) as usize];
-    rng.fill_slice_with_random_uniform(&mut buf);
+/// Generates the extra key [`eval_wopbs`] needs on top of `(client_key,
+/// server_key)`. Build once per key pair and reuse it across calls; it's
+/// comparatively expensive to generate.
+pub fn generate_wopbs_key<const N: usize, S: CipherSuite<N>>(
+    ck: &RadixClientKey,
+    sk: &ServerKey,
+) -> WopbsKey {
+    WopbsKey::new_wopbs_key(ck, sk, &S::WOPBS_PARAMS)
+}
 
-    let mut r = BigInt::default();
-    r.copy_from_le_byte_slice(&buf);
+fn bigint_one<const N: usize>() -> StaticUnsignedBigInt<N> {
+    let mut bytes = vec![0u8; StaticUnsignedBigInt::<N>::BITS.div_ceil(u8::BITS) as usize];
+    bytes[0] = 1;
+    let mut one = StaticUnsignedBigInt::<N>::default();
+    one.copy_from_le_byte_slice(&bytes);
+    one
+}
 
-    r % q
+fn modulus_q<const N: usize, S: CipherSuite<N>>() -> StaticUnsignedBigInt<N> {
+    bigint_one::<N>() << S::LOG2Q as u32
 }
 
-pub fn generate_prf_key() -> Vec<BigInt> {
-    // Initialize PRNG.
+pub fn generate_prf_key<const N: usize, S: CipherSuite<N>>() -> Vec<StaticUnsignedBigInt<N>> {
+    // Seed the AES-128-CTR keystream from the hardware seeder.
     let mut seeder = new_seeder();
     let seed = seeder.seed();
-    let mut rng = RandomGenerator::<ActivatedRandomGenerator>::new(seed);
-
-    let mut bytes = [0u8; 32];
-    rng.fill_slice_with_random_uniform(&mut bytes);
+    let mut sampler = UniformSampler::<N>::new(seed, modulus_q::<N, S>());
 
-    (0..LATTICE_DIM)
-        .map(|_| generate_random_mod_q(&mut rng))
-        .collect()
+    (0..S::LATTICE_DIM).map(|_| sampler.sample()).collect()
 }
 
-pub fn encode<D: Digest>(k: &RadixClientKey, x: &[u8]) -> Vec<Vec<RadixCiphertext>> {
-    // Derive PRG seed from `x`.
-    let hash = D::digest(x);
+/// Derives the PRG seed `encode` (and [`crt::encode_crt`]) expand into the
+/// lattice rows of `h`.
+pub(crate) fn prg_seed<const N: usize, S: CipherSuite<N>>(x: &[u8]) -> tfhe::Seed {
+    let hash = S::Digest::digest(x);
     const SIZE: usize = (u128::BITS / u8::BITS) as usize;
     let seed = u128::from_le_bytes(hash.as_slice()[..SIZE].try_into().unwrap());
-    let seed = tfhe::Seed(seed);
-
-    let mut rng = RandomGenerator::<ActivatedRandomGenerator>::new(seed);
-    const NUM_ROWS: usize = OUT_LEN.div_ceil(P_BYTES);
-    (0..NUM_ROWS)
-        .map(|_| {
-            (0..LATTICE_DIM)
-                .map(|_| {
-                    let r = generate_random_mod_q(&mut rng);
-                    k.encrypt(r)
-                })
-                .collect()
-        })
+    tfhe::Seed(seed)
+}
+
+/// Expands `seed` into the plaintext rows of `h`, before encryption.
+pub(crate) fn sample_h<const N: usize, S: CipherSuite<N>>(
+    seed: tfhe::Seed,
+) -> Vec<Vec<StaticUnsignedBigInt<N>>> {
+    let mut sampler = UniformSampler::<N>::new(seed, modulus_q::<N, S>());
+    let num_rows = S::OUT_LEN.div_ceil(S::LOG2P.div_ceil(u8::BITS as usize));
+    (0..num_rows)
+        .map(|_| (0..S::LATTICE_DIM).map(|_| sampler.sample()).collect())
+        .collect()
+}
+
+pub fn encode<const N: usize, S: CipherSuite<N>>(
+    k: &RadixClientKey,
+    x: &[u8],
+) -> Vec<Vec<RadixCiphertext>> {
+    let seed = prg_seed::<N, S>(x);
+    sample_h::<N, S>(seed)
+        .into_iter()
+        .map(|row| row.into_iter().map(|v| k.encrypt(v)).collect())
+        .collect()
+}
+
+pub fn eval<const N: usize, S: CipherSuite<N>>(
+    fhe_key: &ServerKey,
+    prf_key: &[StaticUnsignedBigInt<N>],
+    h: &[Vec<RadixCiphertext>],
+) -> Vec<RadixCiphertext> {
+    let v = mat_mul_vec(fhe_key, h, prf_key);
+    v.par_iter()
+        .map(|x| fhe_key.scalar_right_shift_parallelized(x, S::LOG2Q - S::LOG2P))
         .collect()
 }
 
-pub fn eval(
+/// LWR rounding via without-padding programmable bootstrapping: `round(v *
+/// p / q) mod p`, evaluated as a lookup table over the full message space
+/// instead of `scalar_right_shift_parallelized`, so it rounds to nearest
+/// where [`eval`] truncates. `p` and `q` are `2^LOG2P` and `2^LOG2Q`, as in
+/// [`eval`]; moduli that aren't powers of two are not supported. The
+/// bootstrap is considerably slower than [`eval`]'s shift.
+pub fn eval_wopbs<const N: usize, S: CipherSuite<N>>(
+    wopbs_key: &WopbsKey,
     fhe_key: &ServerKey,
-    prf_key: &[BigInt],
+    prf_key: &[StaticUnsignedBigInt<N>],
     h: &[Vec<RadixCiphertext>],
 ) -> Vec<RadixCiphertext> {
+    let q = 1u64 << S::LOG2Q;
+    let p = 1u64 << S::LOG2P;
+
     let v = mat_mul_vec(fhe_key, h, prf_key);
     v.par_iter()
-        .map(|x| fhe_key.scalar_right_shift_parallelized(x, LOG2Q - LOG2P))
+        .map(|x| {
+            let x = wopbs_key.keyswitch_to_wopbs_params(fhe_key, x);
+            let lut = wopbs_key.generate_lut_radix(&x, |v| round_div(v, p, q));
+            let rounded = wopbs_key.wopbs(&x, &lut);
+            wopbs_key.keyswitch_to_pbs_params(&rounded)
+        })
         .collect()
 }
 
-pub fn decrypt(k: RadixClientKey, ct: &[RadixCiphertext]) -> [u8; OUT_LEN] {
-    let v: Vec<_> = ct
-        .par_iter()
+/// `round(v * p / q) mod p`, computed in `u128` to avoid overflow on the
+/// intermediate product.
+pub(crate) fn round_div(v: u64, p: u64, q: u64) -> u64 {
+    let (v, p, q) = (v as u128, p as u128, q as u128);
+    (((v * p * 2 + q) / (2 * q)) % p) as u64
+}
+
+pub fn decrypt<const N: usize, S: CipherSuite<N>>(
+    k: RadixClientKey,
+    ct: &[RadixCiphertext],
+) -> Vec<u8> {
+    let p_bytes = S::LOG2P.div_ceil(u8::BITS as usize);
+    ct.par_iter()
         .flat_map(|cti| {
-            const SIZE: usize = Q_BYTES.div_ceil((u64::BITS / u8::BITS) as usize);
-            let dec = k.decrypt::<StaticUnsignedBigInt<SIZE>>(cti);
+            let dec = k.decrypt::<StaticUnsignedBigInt<N>>(cti);
 
-            let mut bytes = [0u8; SIZE * (u64::BITS / u8::BITS) as usize];
+            let mut bytes = vec![0u8; N * (u64::BITS / u8::BITS) as usize];
             dec.copy_to_le_byte_slice(&mut bytes);
-            bytes[..P_BYTES].to_vec()
+            bytes[..p_bytes].to_vec()
         })
-        .collect();
-    v.try_into().unwrap()
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
-    use sha2::Sha256;
-
-    use crate::{decrypt, encode, eval, generate_fhe_keys, generate_prf_key};
+    use crate::suite::DefaultSuite;
+    use crate::{
+        decrypt, encode, eval, eval_wopbs, generate_fhe_keys, generate_prf_key,
+        generate_wopbs_key,
+    };
 
     #[test]
     fn prf() {
-        let (ck, sk) = generate_fhe_keys();
-        let pk = generate_prf_key();
+        let (ck, sk) = generate_fhe_keys::<1, DefaultSuite>();
+        let pk = generate_prf_key::<1, DefaultSuite>();
 
         // Encode input.
         let x = vec![1, 2, 3];
-        let x_enc = encode::<Sha256>(&ck, &x);
+        let x_enc = encode::<1, DefaultSuite>(&ck, &x);
 
         // Eval PRF.
-        let y = eval(&sk, &pk, &x_enc);
+        let y = eval::<1, DefaultSuite>(&sk, &pk, &x_enc);
 
         // Decrypt.
-        let y_dec = decrypt(ck, &y);
+        let y_dec = decrypt::<1, DefaultSuite>(ck, &y);
         println!("y_dec = {:?}", y_dec);
     }
+
+    #[test]
+    fn prf_wopbs() {
+        let (ck, sk) = generate_fhe_keys::<1, DefaultSuite>();
+        let wopbs_key = generate_wopbs_key::<1, DefaultSuite>(&ck, &sk);
+        let pk = generate_prf_key::<1, DefaultSuite>();
+
+        // Encode input.
+        let x = vec![1, 2, 3];
+        let x_enc = encode::<1, DefaultSuite>(&ck, &x);
+
+        // Eval PRF via the WoPBS rounding path.
+        let y = eval_wopbs::<1, DefaultSuite>(&wopbs_key, &sk, &pk, &x_enc);
+
+        // Decrypt.
+        let y_dec = decrypt::<1, DefaultSuite>(ck, &y);
+        println!("y_dec (wopbs) = {:?}", y_dec);
+    }
 }
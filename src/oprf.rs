@@ -0,0 +1,72 @@
+//! A session-oriented OPRF API on top of the low-level [`crate::encode`] /
+//! [`crate::eval`] / [`crate::decrypt`] primitives, named after the
+//! `blind`/`evaluate`/`finalize` flow of an (E)OPRF protocol: the FHE
+//! encryption under the client's key is what blinds the client's input from
+//! the server.
+
+use tfhe::integer::bigint::static_unsigned::StaticUnsignedBigInt;
+use tfhe::integer::{RadixCiphertext, RadixClientKey, ServerKey};
+
+use crate::suite::CipherSuite;
+use crate::{decrypt, encode, eval};
+
+/// What the client retains across a `blind`/`finalize` round trip: the FHE
+/// client key the input was blinded under.
+pub struct ClientState {
+    client_key: RadixClientKey,
+}
+
+/// A client's blinded OPRF input, sent to the server for evaluation.
+pub struct BlindedElement(Vec<Vec<RadixCiphertext>>);
+
+/// The server's evaluation of a [`BlindedElement`], sent back to the client.
+pub struct EvaluationElement(Vec<RadixCiphertext>);
+
+/// Blinds `input` by encrypting it under a fresh encoding of the PRF's
+/// lattice vectors. Send the returned [`BlindedElement`] to the server for
+/// [`evaluate`], and keep the [`ClientState`] to [`finalize`] its response.
+pub fn blind<const N: usize, S: CipherSuite<N>>(
+    client_key: RadixClientKey,
+    input: &[u8],
+) -> (ClientState, BlindedElement) {
+    let message = encode::<N, S>(&client_key, input);
+    (ClientState { client_key }, BlindedElement(message))
+}
+
+/// Evaluates the PRF on a client's [`BlindedElement`] under `prf_key`,
+/// without ever seeing the unblinded input.
+pub fn evaluate<const N: usize, S: CipherSuite<N>>(
+    server_key: &ServerKey,
+    prf_key: &[StaticUnsignedBigInt<N>],
+    message: &BlindedElement,
+) -> EvaluationElement {
+    EvaluationElement(eval::<N, S>(server_key, prf_key, &message.0))
+}
+
+/// Unblinds the server's [`EvaluationElement`] using the client state from
+/// the matching [`blind`] call, yielding the OPRF output.
+pub fn finalize<const N: usize, S: CipherSuite<N>>(
+    client_state: ClientState,
+    message: EvaluationElement,
+) -> Vec<u8> {
+    decrypt::<N, S>(client_state.client_key, &message.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{blind, evaluate, finalize};
+    use crate::suite::DefaultSuite;
+    use crate::{generate_fhe_keys, generate_prf_key};
+
+    #[test]
+    fn oprf_session() {
+        let (ck, sk) = generate_fhe_keys::<1, DefaultSuite>();
+        let pk = generate_prf_key::<1, DefaultSuite>();
+
+        let (client_state, blinded) = blind::<1, DefaultSuite>(ck, &[1, 2, 3]);
+        let evaluated = evaluate::<1, DefaultSuite>(&sk, &pk, &blinded);
+        let output = finalize::<1, DefaultSuite>(client_state, evaluated);
+
+        println!("oprf output = {:?}", output);
+    }
+}
@@ -0,0 +1,93 @@
+use aes::cipher::{KeyIvInit, StreamCipher};
+use aes::Aes128;
+use ctr::Ctr64LE;
+use tfhe::integer::bigint::static_unsigned::StaticUnsignedBigInt;
+
+type Aes128Ctr = Ctr64LE<Aes128>;
+
+/// Number of candidate limbs refilled into a `SeedStream`'s buffer at a time.
+const BUFFER_LIMBS: usize = 128;
+
+fn limb_bytes<const N: usize>() -> usize {
+    StaticUnsignedBigInt::<N>::BITS.div_ceil(u8::BITS) as usize
+}
+
+fn max_value<const N: usize>() -> StaticUnsignedBigInt<N> {
+    let mut max = StaticUnsignedBigInt::<N>::default();
+    max.copy_from_le_byte_slice(&vec![0xffu8; limb_bytes::<N>()]);
+    max
+}
+
+/// An AES-128-CTR keystream that expands a 128-bit seed into a buffered
+/// sequence of `N`-limb-wide candidate values, modeled on prio's PRNG.
+pub struct SeedStream<const N: usize> {
+    cipher: Aes128Ctr,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl<const N: usize> SeedStream<N> {
+    /// Expand `seed` into a fresh keystream.
+    pub fn new(seed: tfhe::Seed) -> Self {
+        let key = seed.0.to_le_bytes();
+        let buf_len = BUFFER_LIMBS * limb_bytes::<N>();
+        SeedStream {
+            cipher: Aes128Ctr::new(&key.into(), &[0u8; 16].into()),
+            buf: vec![0u8; buf_len],
+            pos: buf_len, // force a refill on the first draw
+        }
+    }
+
+    fn refill(&mut self) {
+        self.buf.iter_mut().for_each(|b| *b = 0);
+        self.cipher.apply_keystream(&mut self.buf);
+        self.pos = 0;
+    }
+
+    /// Draw the next candidate value, refilling the buffer as needed.
+    fn next_limb(&mut self) -> StaticUnsignedBigInt<N> {
+        let limb_bytes = limb_bytes::<N>();
+        if self.pos >= self.buf.len() {
+            self.refill();
+        }
+        let mut r = StaticUnsignedBigInt::<N>::default();
+        r.copy_from_le_byte_slice(&self.buf[self.pos..self.pos + limb_bytes]);
+        self.pos += limb_bytes;
+        r
+    }
+}
+
+/// Draws values unbiased-uniform in `0..q` via rejection sampling over a
+/// `SeedStream`, so that `q` need not be a power of two.
+///
+/// Candidates at or above the largest multiple of `q` representable in a
+/// limb are redrawn rather than reduced, which is what makes `r % q` biased
+/// for non-power-of-two `q` in the first place.
+pub struct UniformSampler<const N: usize> {
+    stream: SeedStream<N>,
+    q: StaticUnsignedBigInt<N>,
+    threshold: StaticUnsignedBigInt<N>,
+}
+
+impl<const N: usize> UniformSampler<N> {
+    /// Build a sampler over `0..q`, backed by a fresh AES-128-CTR stream
+    /// expanded from `seed`.
+    pub fn new(seed: tfhe::Seed, q: StaticUnsignedBigInt<N>) -> Self {
+        let threshold = (max_value::<N>() / q) * q;
+        UniformSampler {
+            stream: SeedStream::new(seed),
+            q,
+            threshold,
+        }
+    }
+
+    /// Draw the next value, unbiased-uniform in `0..q`.
+    pub fn sample(&mut self) -> StaticUnsignedBigInt<N> {
+        loop {
+            let candidate = self.stream.next_limb();
+            if candidate < self.threshold {
+                return candidate % self.q;
+            }
+        }
+    }
+}
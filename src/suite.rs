@@ -0,0 +1,44 @@
+use digest::Digest;
+use tfhe::shortint::{ClassicPBSParameters, WopbsParameters};
+
+/// A concrete instantiation of the lattice OPRF: the lattice dimension, the
+/// LWR moduli, the output length, the hash used to derive the PRG seed in
+/// [`crate::encode`], and the FHE parameters to bootstrap under.
+///
+/// `Q_WORDS` is the number of `u64` limbs needed to hold a value mod `q`
+/// (i.e. the width of `StaticUnsignedBigInt<Q_WORDS>`); it's a const
+/// parameter on the trait rather than derived from `LOG2Q`, since stable
+/// Rust can't compute an associated const's value into a type's const
+/// generic parameter.
+pub trait CipherSuite<const Q_WORDS: usize> {
+    /// Hash used to derive the PRG seed from an OPRF input in
+    /// [`crate::encode`].
+    type Digest: Digest;
+
+    const LATTICE_DIM: usize;
+    const LOG2Q: usize;
+    const LOG2P: usize;
+    const OUT_LEN: usize;
+    const FHE_PARAMS: ClassicPBSParameters;
+    /// Key-switching parameters for the WoPBS path ([`crate::eval_wopbs`]),
+    /// paired with `FHE_PARAMS` since the WoPBS key is generated from both.
+    const WOPBS_PARAMS: WopbsParameters;
+}
+
+/// The suite this crate shipped with before it became generic: an 8-wide
+/// lattice, 12-bit modulus rounded down to 8 bits, SHA-256-derived seeds,
+/// and the default `tfhe` PBS parameters.
+pub struct DefaultSuite;
+
+impl CipherSuite<1> for DefaultSuite {
+    type Digest = sha2::Sha256;
+
+    const LATTICE_DIM: usize = 8; // 512;
+    const LOG2Q: usize = 12;
+    const LOG2P: usize = 8;
+    const OUT_LEN: usize = 16;
+    const FHE_PARAMS: ClassicPBSParameters =
+        tfhe::shortint::parameters::PARAM_MESSAGE_2_CARRY_2_KS_PBS;
+    const WOPBS_PARAMS: WopbsParameters =
+        tfhe::shortint::parameters::parameters_wopbs_message_carry::WOPBS_PARAM_MESSAGE_2_CARRY_2_KS_PBS;
+}
@@ -0,0 +1,227 @@
+use rayon::prelude::*;
+use tfhe::core_crypto::seeders::new_seeder;
+use tfhe::integer::bigint::static_unsigned::StaticUnsignedBigInt;
+use tfhe::integer::{RadixCiphertext, ServerKey};
+
+use crate::bigint_util::{mod_inverse, to_u128};
+use crate::modulus_q;
+use crate::sampler::UniformSampler;
+use crate::suite::CipherSuite;
+use crate::vec::mat_mul_vec;
+
+/// One server's Shamir share of a split PRF key, for the threshold / distributed
+/// OPRF. `index` is this share's evaluation point (`1..=n`); n-of-n sharing
+/// is the `t == n` special case, whose shares are still polynomial
+/// evaluations recombined with Lagrange weights, not an additive split.
+#[derive(Clone)]
+pub struct KeyShare<const N: usize> {
+    pub index: u64,
+    pub share: Vec<StaticUnsignedBigInt<N>>,
+}
+
+/// Splits `key` into `n` shares such that any `t` of them reconstruct the
+/// original evaluation via [`combine_partials`]. Each coordinate of `key` is
+/// the constant term of an independent degree-`(t - 1)` polynomial with
+/// random higher coefficients mod `q`; share `i` is that polynomial
+/// evaluated at `x = i`.
+///
+/// Reconstruction relies on Lagrange interpolation mod `q`, which needs `q`
+/// to be prime so that every pairwise difference between share indices is
+/// invertible — panics if `S::LOG2Q` gives a composite `q` (every power of
+/// two except `q = 2`, which is the only prime [`CipherSuite`] can express
+/// today; a suite like [`crate::suite::DefaultSuite`] isn't threshold-safe
+/// under this scheme).
+pub fn split_prf_key<const N: usize, S: CipherSuite<N>>(
+    key: &[StaticUnsignedBigInt<N>],
+    t: usize,
+    n: usize,
+) -> Vec<KeyShare<N>> {
+    assert!(t >= 1 && t <= n, "threshold must satisfy 1 <= t <= n");
+
+    let q = modulus_q::<N, S>();
+    assert!(
+        is_prime(to_u128::<N>(q)),
+        "threshold secret sharing requires a prime q"
+    );
+    let mut seeder = new_seeder();
+    let coeffs: Vec<Vec<StaticUnsignedBigInt<N>>> = key
+        .iter()
+        .map(|&k0| {
+            let mut sampler = UniformSampler::<N>::new(seeder.seed(), q);
+            let mut c = Vec::with_capacity(t);
+            c.push(k0);
+            c.extend((1..t).map(|_| sampler.sample()));
+            c
+        })
+        .collect();
+
+    (1..=n as u64)
+        .map(|index| {
+            let share = coeffs
+                .iter()
+                .map(|c| eval_poly::<N>(c, index, q))
+                .collect();
+            KeyShare { index, share }
+        })
+        .collect()
+}
+
+/// Runs this server's share of the PRF evaluation against `h`, producing an
+/// unrounded partial ciphertext vector for the aggregator to combine via
+/// [`combine_partials`]. Rounding must happen only after combination, never
+/// here, or the LWR rounding error blows up across partials.
+pub fn eval_partial<const N: usize>(
+    fhe_key: &ServerKey,
+    share: &KeyShare<N>,
+    h: &[Vec<RadixCiphertext>],
+) -> Vec<RadixCiphertext> {
+    mat_mul_vec(fhe_key, h, &share.share)
+}
+
+/// Combines partial evaluations from an authorized subset of servers
+/// (`|partials| >= t`) by weighting each by its Lagrange coefficient at
+/// `x = 0`, homomorphically summing, and only then rounding — reproducing
+/// the same output [`eval`](crate::eval) would have produced with the
+/// unsplit key.
+pub fn combine_partials<const N: usize, S: CipherSuite<N>>(
+    fhe_key: &ServerKey,
+    partials: &[(u64, Vec<RadixCiphertext>)],
+) -> Vec<RadixCiphertext> {
+    let q = q_u128::<N, S>();
+    assert!(
+        is_prime(q),
+        "threshold secret sharing requires a prime q"
+    );
+    let indices: Vec<u64> = partials.iter().map(|(i, _)| *i).collect();
+    let out_len = partials[0].1.len();
+
+    let summed: Vec<RadixCiphertext> = (0..out_len)
+        .into_par_iter()
+        .map(|j| {
+            let weighted: Vec<_> = partials
+                .iter()
+                .map(|(idx, v)| {
+                    let lambda = lagrange_coefficient::<N>(*idx, &indices, q);
+                    fhe_key.scalar_mul_parallelized(&v[j], lambda)
+                })
+                .collect();
+            fhe_key
+                .unchecked_sum_ciphertexts_vec_parallelized(weighted)
+                .unwrap()
+        })
+        .collect();
+
+    summed
+        .par_iter()
+        .map(|x| fhe_key.scalar_right_shift_parallelized(x, S::LOG2Q - S::LOG2P))
+        .collect()
+}
+
+/// Evaluates `sum(c[j] * x^j) mod q` via Horner's method.
+fn eval_poly<const N: usize>(
+    c: &[StaticUnsignedBigInt<N>],
+    x: u64,
+    q: StaticUnsignedBigInt<N>,
+) -> StaticUnsignedBigInt<N> {
+    let q = to_u128::<N>(q);
+    let x = x as u128;
+    let mut acc = 0u128;
+    for coeff in c.iter().rev() {
+        acc = (acc * x + to_u128::<N>(*coeff)) % q;
+    }
+    from_u128::<N>(acc)
+}
+
+/// The Lagrange basis coefficient for `idx` at `x = 0`, i.e.
+/// `prod_{j != idx} (-j) / (idx - j) mod q`.
+fn lagrange_coefficient<const N: usize>(
+    idx: u64,
+    indices: &[u64],
+    q: u128,
+) -> StaticUnsignedBigInt<N> {
+    let idx = idx as u128;
+
+    let mut num = 1u128;
+    let mut den = 1u128;
+    for &j in indices {
+        let j = j as u128;
+        if j == idx {
+            continue;
+        }
+        num = (num * ((q - j) % q)) % q;
+        let diff = if j >= idx { q - (j - idx) % q } else { idx - j };
+        den = (den * diff) % q;
+    }
+
+    from_u128::<N>((num * mod_inverse(den, q)) % q)
+}
+
+fn q_u128<const N: usize, S: CipherSuite<N>>() -> u128 {
+    to_u128::<N>(modulus_q::<N, S>())
+}
+
+/// Trial-division primality check, for the `q` values (small enough to fit
+/// `u128`, per [`crate::bigint_util::to_u128`]'s own width limit) this
+/// module's Lagrange interpolation requires to be prime.
+fn is_prime(n: u128) -> bool {
+    if n < 2 {
+        return false;
+    }
+    if n % 2 == 0 {
+        return n == 2;
+    }
+    let mut d = 3u128;
+    while d * d <= n {
+        if n % d == 0 {
+            return false;
+        }
+        d += 2;
+    }
+    true
+}
+
+fn from_u128<const N: usize>(x: u128) -> StaticUnsignedBigInt<N> {
+    let n = (StaticUnsignedBigInt::<N>::BITS.div_ceil(u8::BITS)) as usize;
+    let mut r = StaticUnsignedBigInt::<N>::default();
+    r.copy_from_le_byte_slice(&x.to_le_bytes()[..n]);
+    r
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lagrange_coefficient, to_u128};
+
+    /// `split_prf_key`/`combine_partials` now assert `q` is prime (every
+    /// `CipherSuite` in this tree, including `DefaultSuite`, has a
+    /// power-of-two `q` and so can't reach them), so this exercises the
+    /// Lagrange reconstruction they rely on directly, against a prime
+    /// modulus and *every* 2-of-4 subset — not just the adjacent-index
+    /// pairs the previous version of this test happened to pick, which
+    /// masked a sign bug in non-adjacent subsets.
+    #[test]
+    fn lagrange_coefficient_reconstructs_every_two_of_four_subset_mod_a_prime() {
+        let q: u128 = 4099; // prime, unlike any suite's q in this tree today
+        let indices = [1u64, 2, 3, 4];
+        let k0 = 777u128;
+        let c1 = 222u128; // degree-1 coefficient of a synthetic t=2 polynomial
+        let share = |x: u64| -> u128 { (k0 + c1 * x as u128) % q };
+
+        for i in 0..indices.len() {
+            for j in (i + 1)..indices.len() {
+                let subset = [indices[i], indices[j]];
+                let reconstructed: u128 = subset
+                    .iter()
+                    .map(|&idx| {
+                        let lambda = to_u128::<1>(lagrange_coefficient::<1>(idx, &subset, q));
+                        (lambda * share(idx)) % q
+                    })
+                    .sum::<u128>()
+                    % q;
+                assert_eq!(
+                    reconstructed, k0,
+                    "subset {subset:?} failed to reconstruct k0"
+                );
+            }
+        }
+    }
+}
@@ -1,9 +1,12 @@
 use rayon::prelude::*;
+use tfhe::integer::bigint::static_unsigned::StaticUnsignedBigInt;
 use tfhe::integer::{RadixCiphertext, ServerKey};
 
-use crate::BigInt;
-
-pub fn vec_mul_vec(k: &ServerKey, m: &[RadixCiphertext], v: &[BigInt]) -> RadixCiphertext {
+pub fn vec_mul_vec<const N: usize>(
+    k: &ServerKey,
+    m: &[RadixCiphertext],
+    v: &[StaticUnsignedBigInt<N>],
+) -> RadixCiphertext {
     let v: Vec<_> = m
         .par_iter()
         .zip(v)
@@ -12,17 +15,10 @@ pub fn vec_mul_vec(k: &ServerKey, m: &[RadixCiphertext], v: &[BigInt]) -> RadixC
     k.unchecked_sum_ciphertexts_vec_parallelized(v).unwrap()
 }
 
-pub fn mat_mul_vec(
+pub fn mat_mul_vec<const N: usize>(
     k: &ServerKey,
     m: &[Vec<RadixCiphertext>],
-    v: &[BigInt],
+    v: &[StaticUnsignedBigInt<N>],
 ) -> Vec<RadixCiphertext> {
-    m.par_iter()
-        .map(|row| {
-            let now = std::time::SystemTime::now();
-            let y = vec_mul_vec(k, row, v);
-            println!("mat_mul_vec: vec_mul_vec: elpased: {:?}", now.elapsed());
-            y
-        })
-        .collect()
+    m.par_iter().map(|row| vec_mul_vec(k, row, v)).collect()
 }